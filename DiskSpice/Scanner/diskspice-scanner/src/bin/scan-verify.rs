@@ -46,17 +46,22 @@ fn main() {
 
     println!("Path: {}", path.display());
     println!(
-        "Scanner total: {} ({}), items: {}",
+        "Scanner total (logical): {} ({}), items: {}",
         totals.total_size,
         human_bytes(totals.total_size),
         totals.total_items
     );
+    println!(
+        "Scanner total (allocated): {} ({})",
+        totals.total_allocated,
+        human_bytes(totals.total_allocated)
+    );
     match du_bytes {
         Some(bytes) => {
-            let delta = if bytes > totals.total_size {
-                bytes - totals.total_size
+            let delta = if bytes > totals.total_allocated {
+                bytes - totals.total_allocated
             } else {
-                totals.total_size - bytes
+                totals.total_allocated - bytes
             };
             let delta_pct = if bytes == 0 {
                 0.0
@@ -64,7 +69,11 @@ fn main() {
                 (delta as f64 / bytes as f64) * 100.0
             };
             println!("du -sk: {} ({})", bytes, human_bytes(bytes));
-            println!("Delta: {} ({}%)", human_bytes(delta), delta_pct.round());
+            println!(
+                "Delta vs allocated: {} ({}%)",
+                human_bytes(delta),
+                delta_pct.round()
+            );
         }
         None => println!("du -sk: unavailable"),
     }
@@ -0,0 +1,84 @@
+//! On-disk incremental-scan cache, keyed by directory path.
+//!
+//! Each entry records enough about a directory's last scan (its mtime, its
+//! direct children, and their rolled-up totals) that a later scan can tell
+//! whether the directory changed without descending into it again.
+
+use crate::scanner::FileEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const CACHE_FILE_NAME: &str = "diskspice-scan-cache.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub(crate) struct CachedDir {
+    pub modified: Option<u64>,
+    pub item_count: u64,
+    pub total_size: u64,
+    pub total_allocated: u64,
+    pub children: Vec<FileEntry>,
+}
+
+pub(crate) type ScanCache = HashMap<String, CachedDir>;
+
+fn cache_file_path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join(CACHE_FILE_NAME)
+}
+
+/// Loads the cache written by a previous scan. Missing or unparsable cache
+/// files are treated as an empty cache rather than an error, since a cold
+/// cache is a perfectly normal starting state.
+pub(crate) fn load(cache_dir: &Path) -> ScanCache {
+    fs::read_to_string(cache_file_path(cache_dir))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub(crate) fn save(cache_dir: &Path, cache: &ScanCache) -> io::Result<()> {
+    fs::create_dir_all(cache_dir)?;
+    let json =
+        serde_json::to_string(cache).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(cache_file_path(cache_dir), json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir_path;
+
+    #[test]
+    fn load_missing_cache_returns_empty_map() {
+        let cache_dir = temp_dir_path("cache_missing");
+        let cache = load(&cache_dir);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let cache_dir = temp_dir_path("cache_roundtrip");
+        let mut cache = ScanCache::new();
+        cache.insert(
+            "/tmp/example".to_string(),
+            CachedDir {
+                modified: Some(12345),
+                item_count: 3,
+                total_size: 300,
+                total_allocated: 4096 * 3,
+                children: Vec::new(),
+            },
+        );
+
+        save(&cache_dir, &cache).expect("save cache");
+        let loaded = load(&cache_dir);
+
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded["/tmp/example"].item_count, 3);
+        assert_eq!(loaded["/tmp/example"].total_size, 300);
+
+        fs::remove_dir_all(cache_dir).ok();
+    }
+}
@@ -0,0 +1,183 @@
+//! Size -> partial-hash -> full-hash funnel for duplicate-file detection.
+//!
+//! Files with a unique size can never be duplicates, so callers only need
+//! to hand us paths already grouped by `size`. From there we narrow each
+//! size bucket down with a cheap partial hash (first 4096 bytes) before
+//! paying for a full-content hash, matching the funnel ddh uses.
+
+use crate::scanner::ScanMessage;
+use siphasher::sip128::{Hash128, Hasher128, SipHasher13};
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::path::Path;
+
+const PARTIAL_HASH_BYTES: usize = 4096;
+const FULL_HASH_BLOCK_BYTES: usize = 4096;
+
+/// One candidate bucket on its way through the funnel: files that still
+/// agree on `size` and, once computed, `partial_hash`/`full_hash`.
+#[derive(Debug, Clone)]
+struct DuplicateCandidate {
+    size: u64,
+    partial_hash: Option<u128>,
+    full_hash: Option<u128>,
+    paths: Vec<String>,
+}
+
+fn to_u128(hash: Hash128) -> u128 {
+    ((hash.h1 as u128) << 64) | hash.h2 as u128
+}
+
+fn hash_partial(path: &Path) -> io::Result<u128> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0u8; PARTIAL_HASH_BYTES];
+    let read = file.read(&mut buffer)?;
+    let mut hasher = SipHasher13::new();
+    hasher.write(&buffer[..read]);
+    Ok(to_u128(hasher.finish128()))
+}
+
+fn hash_full(path: &Path) -> io::Result<u128> {
+    let mut file = File::open(path)?;
+    let mut hasher = SipHasher13::new();
+    let mut buffer = [0u8; FULL_HASH_BLOCK_BYTES];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.write(&buffer[..read]);
+    }
+    Ok(to_u128(hasher.finish128()))
+}
+
+/// Narrows a size bucket down to groups that also share a partial hash.
+/// Files that fail to read (removed mid-scan, permission errors, ...) are
+/// dropped from consideration rather than treated as an error.
+fn group_by_partial_hash(size: u64, paths: Vec<String>) -> Vec<DuplicateCandidate> {
+    let mut buckets: HashMap<u128, Vec<String>> = HashMap::new();
+    for path in paths {
+        if let Ok(hash) = hash_partial(Path::new(&path)) {
+            buckets.entry(hash).or_default().push(path);
+        }
+    }
+
+    buckets
+        .into_iter()
+        .filter(|(_, paths)| paths.len() >= 2)
+        .map(|(hash, paths)| DuplicateCandidate {
+            size,
+            partial_hash: Some(hash),
+            full_hash: None,
+            paths,
+        })
+        .collect()
+}
+
+/// Narrows a partial-hash group down to groups that also share a full
+/// content hash. What survives is a confirmed duplicate set.
+fn group_by_full_hash(candidate: DuplicateCandidate) -> Vec<DuplicateCandidate> {
+    let mut buckets: HashMap<u128, Vec<String>> = HashMap::new();
+    for path in candidate.paths {
+        if let Ok(hash) = hash_full(Path::new(&path)) {
+            buckets.entry(hash).or_default().push(path);
+        }
+    }
+
+    buckets
+        .into_iter()
+        .filter(|(_, paths)| paths.len() >= 2)
+        .map(|(hash, paths)| DuplicateCandidate {
+            size: candidate.size,
+            partial_hash: candidate.partial_hash,
+            full_hash: Some(hash),
+            paths,
+        })
+        .collect()
+}
+
+/// Runs the full funnel over paths already grouped by `size`, returning one
+/// `ScanMessage::DuplicateGroup` per confirmed byte-identical set.
+pub(crate) fn find_duplicate_groups(by_size: HashMap<u64, Vec<String>>) -> Vec<ScanMessage> {
+    let mut messages = Vec::new();
+
+    for (size, paths) in by_size {
+        if paths.len() < 2 {
+            continue; // a unique size can never be a duplicate
+        }
+
+        for partial_group in group_by_partial_hash(size, paths) {
+            for full_group in group_by_full_hash(partial_group) {
+                let hash = full_group.full_hash.expect("set by group_by_full_hash");
+                messages.push(ScanMessage::DuplicateGroup {
+                    size: full_group.size,
+                    hash: format!("{:032x}", hash),
+                    paths: full_group.paths,
+                });
+            }
+        }
+    }
+
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_temp_dir, write_file};
+    use std::fs;
+
+    #[test]
+    fn finds_identical_files_and_skips_unique_sizes() {
+        let root = create_temp_dir("dedup_identical");
+        write_file(&root.join("a.bin"), b"same contents");
+        write_file(&root.join("b.bin"), b"same contents");
+        write_file(&root.join("c.bin"), b"different, longer contents");
+
+        let mut by_size: HashMap<u64, Vec<String>> = HashMap::new();
+        for name in ["a.bin", "b.bin", "c.bin"] {
+            let path = root.join(name);
+            let size = fs::metadata(&path).unwrap().len();
+            by_size
+                .entry(size)
+                .or_default()
+                .push(path.display().to_string());
+        }
+
+        let groups = find_duplicate_groups(by_size);
+
+        assert_eq!(groups.len(), 1);
+        match &groups[0] {
+            ScanMessage::DuplicateGroup { size, paths, .. } => {
+                assert_eq!(*size, 13);
+                assert_eq!(paths.len(), 2);
+            }
+            other => panic!("expected DuplicateGroup, got {:?}", other),
+        }
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
+    #[test]
+    fn same_size_different_contents_is_not_a_duplicate() {
+        let root = create_temp_dir("dedup_collision");
+        write_file(&root.join("a.bin"), b"aaaaaaaaaa");
+        write_file(&root.join("b.bin"), b"bbbbbbbbbb");
+
+        let mut by_size: HashMap<u64, Vec<String>> = HashMap::new();
+        by_size.insert(
+            10,
+            vec![
+                root.join("a.bin").display().to_string(),
+                root.join("b.bin").display().to_string(),
+            ],
+        );
+
+        let groups = find_duplicate_groups(by_size);
+        assert!(groups.is_empty());
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+}
@@ -0,0 +1,269 @@
+//! Integrity checks for files the walk has already classified as
+//! `image`/`archive`/`document`/`audio`. Only formats with a cheap,
+//! well-known header and/or trailer are validated; anything else in those
+//! categories (svg, heic, m4a, ...) is left unchecked rather than risking a
+//! false positive from an incomplete parser.
+
+use crate::scanner::ScanMessage;
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const HEADER_BYTES: usize = 16;
+const FOOTER_BYTES: usize = 64;
+
+fn read_header(path: &Path) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let mut buf = vec![0u8; HEADER_BYTES];
+    let read = file.read(&mut buf)?;
+    buf.truncate(read);
+    Ok(buf)
+}
+
+fn read_footer(path: &Path) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let take = FOOTER_BYTES.min(len as usize);
+    file.seek(SeekFrom::End(-(take as i64)))?;
+    let mut buf = vec![0u8; take];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Checks a file whose extension names a format we know how to validate.
+/// A header that doesn't match what the extension promises is itself a
+/// broken file (e.g. a `.jpg` that's really garbage, or some other format
+/// entirely) rather than something to skip; so is a missing trailer marker.
+fn check_image(path: &Path) -> Option<String> {
+    let ext = extension(path);
+    let header = read_header(path).ok()?;
+
+    match ext.as_str() {
+        "jpg" | "jpeg" => {
+            if !header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+                return Some("JPEG is missing its start-of-image marker".to_string());
+            }
+            let footer = read_footer(path).ok()?;
+            if !footer.ends_with(&[0xFF, 0xD9]) {
+                return Some("JPEG is missing its end-of-image marker".to_string());
+            }
+        }
+        "png" => {
+            if !header.starts_with(b"\x89PNG\r\n\x1a\n") {
+                return Some("PNG is missing its signature".to_string());
+            }
+            let footer = read_footer(path).ok()?;
+            if !contains(&footer, b"IEND") {
+                return Some("PNG is missing its IEND chunk".to_string());
+            }
+        }
+        "gif" => {
+            if !(header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a")) {
+                return Some("GIF is missing its signature".to_string());
+            }
+            let footer = read_footer(path).ok()?;
+            if footer.last() != Some(&0x3B) {
+                return Some("GIF is missing its trailer byte".to_string());
+            }
+        }
+        // bmp/svg/webp/heic/... have no parser implemented here; leave
+        // unchecked rather than risk a false positive.
+        _ => {}
+    }
+
+    None
+}
+
+fn check_archive(path: &Path) -> Option<String> {
+    let ext = extension(path);
+    if ext != "zip" {
+        return None;
+    }
+
+    let header = read_header(path).ok()?;
+    let is_zip = header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06");
+    if !is_zip {
+        return Some("zip is missing its local-file/central-directory header".to_string());
+    }
+
+    let footer = read_footer(path).ok()?;
+    if !contains(&footer, b"PK\x05\x06") {
+        return Some("zip is missing its end-of-central-directory record".to_string());
+    }
+
+    None
+}
+
+fn check_document(path: &Path) -> Option<String> {
+    let ext = extension(path);
+    if ext != "pdf" {
+        return None;
+    }
+
+    let header = read_header(path).ok()?;
+    if !header.starts_with(b"%PDF-") {
+        return Some("PDF is missing its %PDF- header".to_string());
+    }
+
+    let footer = read_footer(path).ok()?;
+    if !contains(&footer, b"startxref") || !contains(&footer, b"%%EOF") {
+        return Some("PDF trailer is missing startxref/%%EOF".to_string());
+    }
+
+    None
+}
+
+fn check_audio(path: &Path) -> Option<String> {
+    let header = read_header(path).ok()?;
+
+    if header.starts_with(b"RIFF") {
+        if header.len() < 12 || &header[8..12] != b"WAVE" {
+            return Some("RIFF container is missing its WAVE signature".to_string());
+        }
+    } else if header.starts_with(b"fLaC") {
+        // Signature alone is enough to know this is a FLAC stream; no cheap
+        // trailer to check, so a matching header is considered healthy.
+    } else if header.starts_with(b"OggS") {
+        // Same reasoning as FLAC: container signature only.
+    }
+
+    None
+}
+
+/// Runs the check for a single candidate, returning `(kind, message)` if it
+/// looks broken.
+fn check_file(path: &Path, file_type: &str) -> Option<(String, String)> {
+    let message = match file_type {
+        "image" => check_image(path),
+        "archive" => check_archive(path),
+        "document" => check_document(path),
+        "audio" => check_audio(path),
+        _ => None,
+    }?;
+    Some((file_type.to_string(), message))
+}
+
+/// Checks every `(path, file_type)` candidate concurrently on the rayon
+/// pool, since each check is dominated by I/O rather than CPU.
+pub(crate) fn check_integrity(candidates: Vec<(String, String)>) -> Vec<ScanMessage> {
+    candidates
+        .par_iter()
+        .filter_map(|(path, file_type)| {
+            check_file(Path::new(path), file_type).map(|(kind, message)| ScanMessage::Broken {
+                path: path.clone(),
+                kind,
+                message,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_temp_dir, write_file};
+    use std::fs;
+
+    #[test]
+    fn flags_jpeg_missing_end_of_image_marker() {
+        let root = create_temp_dir("integrity_jpeg_truncated");
+        let path = root.join("broken.jpg");
+        write_file(&path, &[0xFF, 0xD8, 0xFF, 0x00, 0x01, 0x02]);
+
+        let candidates = vec![(path.display().to_string(), "image".to_string())];
+        let broken = check_integrity(candidates);
+
+        assert_eq!(broken.len(), 1);
+        match &broken[0] {
+            ScanMessage::Broken { kind, .. } => assert_eq!(kind, "image"),
+            other => panic!("expected Broken, got {:?}", other),
+        }
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
+    #[test]
+    fn accepts_well_formed_jpeg() {
+        let root = create_temp_dir("jpeg_ok");
+        let path = root.join("ok.jpg");
+        write_file(&path, &[0xFF, 0xD8, 0xFF, 0x00, 0x01, 0xFF, 0xD9]);
+
+        let candidates = vec![(path.display().to_string(), "image".to_string())];
+        let broken = check_integrity(candidates);
+
+        assert!(broken.is_empty());
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
+    #[test]
+    fn flags_jpeg_extension_with_garbage_header() {
+        let root = create_temp_dir("jpeg_garbage");
+        let path = root.join("totally_not_a_real.jpg");
+        write_file(&path, b"this is plain text, not a jpeg at all");
+
+        let candidates = vec![(path.display().to_string(), "image".to_string())];
+        let broken = check_integrity(candidates);
+
+        assert_eq!(
+            broken.len(),
+            1,
+            "a .jpg whose header doesn't match JPEG is corrupt, not merely unchecked"
+        );
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
+    #[test]
+    fn flags_zip_extension_with_garbage_header() {
+        let root = create_temp_dir("zip_garbage");
+        let path = root.join("archive.zip");
+        write_file(&path, b"this is plain text, not a zip at all");
+
+        let candidates = vec![(path.display().to_string(), "archive".to_string())];
+        let broken = check_integrity(candidates);
+
+        assert_eq!(broken.len(), 1);
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
+    #[test]
+    fn flags_pdf_extension_with_garbage_header() {
+        let root = create_temp_dir("pdf_garbage");
+        let path = root.join("report.pdf");
+        write_file(&path, b"this is plain text, not a pdf at all");
+
+        let candidates = vec![(path.display().to_string(), "document".to_string())];
+        let broken = check_integrity(candidates);
+
+        assert_eq!(broken.len(), 1);
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
+    #[test]
+    fn skips_unrecognized_headers_without_false_positives() {
+        let root = create_temp_dir("svg_skip");
+        let path = root.join("vector.svg");
+        write_file(&path, b"<svg xmlns=\"http://www.w3.org/2000/svg\"></svg>");
+
+        let candidates = vec![(path.display().to_string(), "image".to_string())];
+        let broken = check_integrity(candidates);
+
+        assert!(broken.is_empty(), "unrecognized image formats aren't validated");
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+}
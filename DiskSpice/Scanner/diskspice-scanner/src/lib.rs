@@ -1,4 +1,9 @@
+mod cache;
+mod dedup;
+mod integrity;
 mod scanner;
+#[cfg(test)]
+mod test_support;
 
 use std::ffi::CStr;
 use std::os::raw::c_char;
@@ -1,15 +1,65 @@
+mod cache;
+mod dedup;
+mod integrity;
 mod scanner;
+#[cfg(test)]
+mod test_support;
 
 use scanner::{ControlCommand, Scanner};
 use std::env;
 use std::io::{self, BufRead};
+use std::process;
 use std::thread;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let path = args.get(1).map(|s| s.as_str()).unwrap_or(".");
+    let mut path = ".".to_string();
+    let mut find_duplicates = false;
+    let mut detect_by_content = false;
+    let mut progress = false;
+    let mut check_integrity = false;
+    let mut parallel = false;
+    let mut threads: usize = 0;
+    let mut cache_dir: Option<String> = None;
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--find-duplicates" => find_duplicates = true,
+            "--detect-by-content" => detect_by_content = true,
+            "--progress" => progress = true,
+            "--check-integrity" => check_integrity = true,
+            "--parallel" => parallel = true,
+            "--threads" => threads = iter.next().and_then(|s| s.parse().ok()).unwrap_or(0),
+            "--cache-dir" => cache_dir = iter.next().cloned(),
+            other => path = other.to_string(),
+        }
+    }
+
+    let has_cache_dir = cache_dir.is_some();
+
+    // The rayon-based parallel walk only tracks size/item/allocated totals
+    // (see `ParallelState`); it doesn't collect duplicate candidates or
+    // integrity candidates, doesn't emit progress, and doesn't read or
+    // write the incremental cache. Running `--parallel` alongside any of
+    // these would silently drop the feature the flag asked for, so reject
+    // the combination up front instead.
+    if parallel && (find_duplicates || check_integrity || progress || has_cache_dir) {
+        eprintln!(
+            "--parallel does not yet support --find-duplicates, --check-integrity, \
+             --progress, or --cache-dir; drop --parallel or those flags"
+        );
+        process::exit(1);
+    }
 
     let (mut scanner, control_tx) = Scanner::with_control_channel();
+    scanner.set_find_duplicates(find_duplicates);
+    scanner.set_detect_by_content(detect_by_content);
+    scanner.set_progress(progress);
+    scanner.set_check_integrity(check_integrity);
+    scanner.set_threads(threads);
+    if let Some(dir) = cache_dir {
+        scanner.set_cache_dir(dir);
+    }
 
     // Spawn thread to read stdin for commands
     let stdin_tx = control_tx.clone();
@@ -35,5 +85,17 @@ fn main() {
         }
     });
 
-    scanner.scan(path);
+    if parallel {
+        scanner.scan_parallel(&path);
+    } else {
+        scanner.scan(&path);
+    }
+
+    // Refresh requests only make sense against a persisted cache; without
+    // one there's nothing for `refresh` to serve, and blocking on
+    // `recv()` forever would turn every basic one-shot invocation into a
+    // process that never exits.
+    if has_cache_dir {
+        scanner.serve_refresh_requests();
+    }
 }
@@ -1,18 +1,29 @@
+use crate::cache::{self, CachedDir, ScanCache};
+use crate::dedup;
+use crate::integrity;
 use crossbeam_channel::{bounded, Receiver, Sender, TryRecvError};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::io::{self, BufWriter, Write};
-use std::path::Path;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::io::{self, BufWriter, Read, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileEntry {
     pub path: String,
     pub name: String,
     pub size: u64,
+    /// Bytes actually allocated on disk (`blocks() * 512`), which can
+    /// diverge from `size` for sparse files or small files that round up
+    /// to a block. For directories this is the recursive sum over children
+    /// plus the directory's own inode blocks, matching what `du` reports.
+    pub allocated_size: u64,
     pub is_dir: bool,
     pub is_symlink: bool,
     pub modified: Option<u64>, // Unix timestamp
@@ -40,7 +51,29 @@ pub enum ScanMessage {
     #[serde(rename = "status")]
     Status { status: String },
     #[serde(rename = "done")]
-    Done { total_size: u64, total_items: u64 },
+    Done {
+        total_size: u64,
+        total_items: u64,
+        total_allocated: u64,
+    },
+    #[serde(rename = "duplicate_group")]
+    DuplicateGroup {
+        size: u64,
+        hash: String,
+        paths: Vec<String>,
+    },
+    #[serde(rename = "progress")]
+    Progress {
+        items_done: u64,
+        items_total: u64,
+        bytes_done: u64,
+    },
+    #[serde(rename = "broken")]
+    Broken {
+        path: String,
+        kind: String,
+        message: String,
+    },
 }
 
 pub struct Scanner {
@@ -50,12 +83,32 @@ pub struct Scanner {
     control_rx: Option<Receiver<ControlCommand>>,
     pending_entries: usize,
     flush_batch_size: usize,
+    threads: usize,
+    find_duplicates: bool,
+    dedup_by_size: HashMap<u64, Vec<String>>,
+    cache_dir: Option<PathBuf>,
+    cache: ScanCache,
+    pending_cache: ScanCache,
+    detect_by_content: bool,
+    progress_enabled: bool,
+    progress_items_total: u64,
+    progress_items_done: u64,
+    progress_bytes_done: u64,
+    progress_last_emit: Option<Instant>,
+    check_integrity: bool,
+    integrity_candidates: Vec<(String, String)>,
 }
 
+/// Throttle for `ScanMessage::Progress`: emitted at most once per this many
+/// milliseconds, or once every `flush_batch_size` items, whichever comes
+/// first, so a fast scan doesn't flood stdout with progress lines.
+const PROGRESS_THROTTLE: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Clone, Copy)]
 pub struct ScanTotals {
     pub total_size: u64,
     pub total_items: u64,
+    pub total_allocated: u64,
 }
 
 impl Scanner {
@@ -67,6 +120,20 @@ impl Scanner {
             control_rx: None,
             pending_entries: 0,
             flush_batch_size: 256,
+            threads: 0,
+            find_duplicates: false,
+            dedup_by_size: HashMap::new(),
+            cache_dir: None,
+            cache: ScanCache::new(),
+            pending_cache: ScanCache::new(),
+            detect_by_content: false,
+            progress_enabled: false,
+            progress_items_total: 0,
+            progress_items_done: 0,
+            progress_bytes_done: 0,
+            progress_last_emit: None,
+            check_integrity: false,
+            integrity_candidates: Vec::new(),
         }
     }
 
@@ -79,10 +146,72 @@ impl Scanner {
             control_rx: Some(rx),
             pending_entries: 0,
             flush_batch_size: 256,
+            threads: 0,
+            find_duplicates: false,
+            dedup_by_size: HashMap::new(),
+            cache_dir: None,
+            cache: ScanCache::new(),
+            pending_cache: ScanCache::new(),
+            detect_by_content: false,
+            progress_enabled: false,
+            progress_items_total: 0,
+            progress_items_done: 0,
+            progress_bytes_done: 0,
+            progress_last_emit: None,
+            check_integrity: false,
+            integrity_candidates: Vec::new(),
         };
         (scanner, tx)
     }
 
+    /// Enable the size -> partial-hash -> full-hash duplicate-detection
+    /// funnel; emits `ScanMessage::DuplicateGroup` entries at the end of
+    /// `scan`. Disabled by default so the single-pass walk is unaffected.
+    pub fn set_find_duplicates(&mut self, enabled: bool) {
+        self.find_duplicates = enabled;
+    }
+
+    /// Enable the incremental-scan cache, loading whatever a previous scan
+    /// left in `dir`. With a cache dir set, `scan`/`refresh` skip descending
+    /// into directories whose mtime and child set haven't changed, reusing
+    /// the stored totals and children instead.
+    pub fn set_cache_dir(&mut self, dir: impl Into<PathBuf>) {
+        let dir = dir.into();
+        self.cache = cache::load(&dir);
+        self.cache_dir = Some(dir);
+    }
+
+    /// Set the rayon thread count used by `scan_parallel`. `0` keeps rayon's
+    /// default (one worker per logical core).
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+    }
+
+    /// Enable magic-byte content sniffing as a fallback for files whose
+    /// extension doesn't match a known category (no extension, misnamed
+    /// files, things like `Makefile`). Disabled by default since it costs an
+    /// extra file open/read per `"other"`-classified file.
+    pub fn set_detect_by_content(&mut self, enabled: bool) {
+        self.detect_by_content = enabled;
+    }
+
+    /// Enable deterministic progress reporting: `scan` first does a cheap
+    /// pre-count pass (no hashing, no type detection, no cache writes) to
+    /// learn the total item count, then emits throttled
+    /// `ScanMessage::Progress` updates during the real walk. Disabled by
+    /// default since the pre-count pass doubles directory reads.
+    pub fn set_progress(&mut self, enabled: bool) {
+        self.progress_enabled = enabled;
+    }
+
+    /// Enable the integrity-check pass: files classified as
+    /// `image`/`archive`/`document`/`audio` are validated on the rayon pool
+    /// at the end of `scan`, emitting `ScanMessage::Broken` for any that
+    /// fail. Disabled by default since it re-reads every matching file.
+    pub fn set_check_integrity(&mut self, enabled: bool) {
+        self.check_integrity = enabled;
+    }
+
     /// Check for control commands and handle them
     fn check_control(&mut self) -> bool {
         if self.cancelled.load(Ordering::Relaxed) {
@@ -156,21 +285,292 @@ impl Scanner {
             return;
         }
 
-        let (total_size, total_items) = self.scan_directory(path, true);
+        if self.progress_enabled {
+            self.progress_items_total = self.count_items(path);
+            self.progress_items_done = 0;
+            self.progress_bytes_done = 0;
+            self.progress_last_emit = None;
+        }
+
+        let (total_size, total_items, total_allocated) = self.scan_directory(path, true);
+
+        if self.find_duplicates {
+            let by_size = std::mem::take(&mut self.dedup_by_size);
+            for message in dedup::find_duplicate_groups(by_size) {
+                self.emit(message);
+            }
+        }
+
+        if self.check_integrity {
+            let candidates = std::mem::take(&mut self.integrity_candidates);
+            for message in integrity::check_integrity(candidates) {
+                self.emit(message);
+            }
+        }
+
+        if self.progress_enabled {
+            self.emit(ScanMessage::Progress {
+                items_done: total_items,
+                items_total: self.progress_items_total,
+                bytes_done: total_size,
+            });
+        }
 
         self.emit(ScanMessage::Done {
             total_size,
             total_items,
+            total_allocated,
         });
+
+        self.flush_cache();
+    }
+
+    /// Cheap recursive count of files/symlinks under `path`, used only to
+    /// size the progress bar before the real walk starts: no metadata beyond
+    /// `is_dir`/`is_symlink`, no hashing, no type detection, no cache
+    /// interaction. Still respects `check_control` so pausing/cancelling
+    /// works while counting, not just while walking.
+    fn count_items(&mut self, path: &Path) -> u64 {
+        if !self.check_control() {
+            return 0;
+        }
+
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(_) => return 0,
+        };
+
+        let mut count = 0;
+        for entry in entries.flatten() {
+            if !self.check_control() {
+                break;
+            }
+            let is_symlink = entry.file_type().map(|ft| ft.is_symlink()).unwrap_or(false);
+            match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() && !is_symlink => {
+                    count += self.count_items(&entry.path());
+                }
+                Ok(_) => count += 1,
+                Err(_) => {}
+            }
+        }
+        count
     }
 
-    fn scan_directory(&mut self, path: &Path, emit_entries: bool) -> (u64, u64) {
+    /// Emits a throttled `ScanMessage::Progress` if enough items have been
+    /// processed or enough time has passed since the last one.
+    fn maybe_emit_progress(&mut self) {
+        if !self.progress_enabled {
+            return;
+        }
+
+        let now = Instant::now();
+        let due_by_count = self
+            .progress_items_done
+            .is_multiple_of(self.flush_batch_size as u64);
+        let due_by_time = self
+            .progress_last_emit
+            .map(|last| now.duration_since(last) >= PROGRESS_THROTTLE)
+            .unwrap_or(true);
+        if !due_by_count && !due_by_time {
+            return;
+        }
+
+        self.progress_last_emit = Some(now);
+        self.emit(ScanMessage::Progress {
+            items_done: self.progress_items_done,
+            items_total: self.progress_items_total,
+            bytes_done: self.progress_bytes_done,
+        });
+    }
+
+    /// Re-walks a single subtree instead of the whole scanned root, reusing
+    /// the on-disk cache for any unchanged descendant directories. `path`
+    /// itself is always re-read (that's the point of asking for a refresh);
+    /// nested directories still skip a full re-walk if their mtime and
+    /// child set match what's cached. This is what services a
+    /// `ControlCommand::Refresh` from a long-lived front-end.
+    pub fn refresh(&mut self, root_path: &str) {
+        let path = Path::new(root_path);
+        if !path.exists() {
+            self.emit_error(root_path, "Path does not exist");
+            return;
+        }
+
+        self.emit(ScanMessage::Status {
+            status: format!("refreshing:{}", root_path),
+        });
+
+        let (total_size, _total_items, _total_allocated) =
+            self.scan_directory_internal(path, true, false);
+
+        self.emit(ScanMessage::FolderComplete {
+            path: root_path.to_string(),
+            total_size,
+        });
+
+        self.flush_cache();
+    }
+
+    /// Blocks waiting for further `ControlCommand`s after an initial
+    /// `scan`/`scan_parallel` has completed, servicing `Refresh` requests
+    /// via the incremental cache until `Cancel` arrives or the channel
+    /// disconnects. Intended for a long-lived front-end that keeps the
+    /// scanner process around between user-triggered refreshes.
+    pub fn serve_refresh_requests(&mut self) {
+        loop {
+            let rx = match &self.control_rx {
+                Some(rx) => rx.clone(),
+                None => return,
+            };
+            match rx.recv() {
+                Ok(ControlCommand::Refresh(path)) => self.refresh(&path),
+                Ok(ControlCommand::Cancel) => {
+                    self.cancelled.store(true, Ordering::Relaxed);
+                    return;
+                }
+                Ok(ControlCommand::Pause) | Ok(ControlCommand::Resume) => {
+                    // Pause/resume only make sense while a walk is active.
+                }
+                Err(_) => return,
+            }
+        }
+    }
+
+    /// Merges whatever directories were (re)walked this run into the cache,
+    /// replacing older records for the same paths and keeping all others
+    /// (including records of directories that no longer exist), and, if a
+    /// cache dir is configured, persists it for the next cold start.
+    fn flush_cache(&mut self) {
+        if self.pending_cache.is_empty() {
+            return;
+        }
+        let pending = std::mem::take(&mut self.pending_cache);
+        self.cache.extend(pending);
+        if let Some(dir) = self.cache_dir.clone() {
+            cache::save(&dir, &self.cache).ok();
+        }
+    }
+
+    /// Like `scan`, but walks sibling subdirectories concurrently on a rayon
+    /// thread pool instead of single-threaded recursion. Unlike `scan`, it
+    /// does not collect duplicate or integrity candidates, emit progress, or
+    /// read or write the incremental cache.
+    ///
+    /// Output is still coherent: every entry is written through a single
+    /// mutex-guarded `BufWriter`, so JSON lines from different workers never
+    /// interleave mid-message, and `total_size`/`total_items`/`total_allocated`
+    /// are accumulated with atomics rather than threaded through return values.
+    ///
+    /// Pause/cancel handling is centralized: a single watcher thread spawned
+    /// by this method is the only reader of `control_rx` during the walk and
+    /// the only place `paused`/`cancelled` are set. Worker threads read those
+    /// flags and park on a condvar while paused rather than each spinning a
+    /// sleep loop, so they never race each other over the control channel.
+    pub fn scan_parallel(&mut self, root_path: &str) {
+        let path = Path::new(root_path);
+        if !path.exists() {
+            self.emit_error(root_path, "Path does not exist");
+            return;
+        }
+
+        let pool = match rayon::ThreadPoolBuilder::new()
+            .num_threads(self.threads)
+            .build()
+        {
+            Ok(pool) => pool,
+            Err(e) => {
+                self.emit_error(root_path, &e.to_string());
+                return;
+            }
+        };
+
+        let state = Arc::new(ParallelState {
+            writer: Arc::new(Mutex::new(BufWriter::new(io::stdout()))),
+            paused: Arc::clone(&self.paused),
+            cancelled: Arc::clone(&self.cancelled),
+            watcher_done: AtomicBool::new(false),
+            total_size: AtomicU64::new(0),
+            total_items: AtomicU64::new(0),
+            total_allocated: AtomicU64::new(0),
+            detect_by_content: self.detect_by_content,
+            pause_lock: Mutex::new(()),
+            pause_condvar: Condvar::new(),
+        });
+
+        // The coordinator (this thread, plus the watcher thread it spawns
+        // below) is the only place that drains `control_rx`. Worker threads
+        // spawned by rayon only ever read `state.paused`/`state.cancelled`,
+        // so they never contend over the channel. Clone the receiver rather
+        // than `take`ing it: `self.control_rx` needs to stay usable so a
+        // later `serve_refresh_requests()` call can still service refreshes.
+        let watcher = self.control_rx.clone().map(|rx| {
+            let watcher_state = Arc::clone(&state);
+            thread::spawn(move || run_control_watcher(&rx, &watcher_state))
+        });
+
+        pool.install(|| scan_directory_parallel(path, &state));
+
+        state.watcher_done.store(true, Ordering::Relaxed);
+        if let Some(handle) = watcher {
+            handle.join().ok();
+        }
+
+        if let Ok(mut writer) = state.writer.lock() {
+            writer.flush().ok();
+        }
+
+        let total_size = state.total_size.load(Ordering::Relaxed);
+        let total_items = state.total_items.load(Ordering::Relaxed);
+        let total_allocated = state.total_allocated.load(Ordering::Relaxed);
+
+        self.emit(ScanMessage::Done {
+            total_size,
+            total_items,
+            total_allocated,
+        });
+    }
+
+    fn scan_directory(&mut self, path: &Path, emit_entries: bool) -> (u64, u64, u64) {
+        self.scan_directory_internal(path, emit_entries, true)
+    }
+
+    /// Core recursive walk. `allow_cache` gates whether `path` itself may be
+    /// served from the cache; it is always `true` for recursive calls so a
+    /// `refresh` of one subtree still benefits from unchanged descendants,
+    /// but `false` for the subtree `refresh` was explicitly asked to re-read.
+    fn scan_directory_internal(
+        &mut self,
+        path: &Path,
+        emit_entries: bool,
+        allow_cache: bool,
+    ) -> (u64, u64, u64) {
         let mut total_size: u64 = 0;
         let mut total_items: u64 = 0;
+        let mut total_allocated: u64 = 0;
 
         // Check for control commands at the start of each directory
         if !self.check_control() {
-            return (0, 0);
+            return (0, 0, 0);
+        }
+
+        if let Some(cached) = allow_cache.then(|| self.reuse_cached_dir(path)).flatten() {
+            if emit_entries {
+                for child in &cached.children {
+                    self.emit_entry(child.clone());
+                }
+                self.emit(ScanMessage::FolderComplete {
+                    path: path.display().to_string(),
+                    total_size: cached.total_size,
+                });
+            }
+            self.remember_cached_dir(path, cached.clone());
+            if self.progress_enabled {
+                self.progress_items_done += cached.item_count;
+                self.progress_bytes_done += cached.total_size;
+                self.maybe_emit_progress();
+            }
+            return (cached.total_size, cached.item_count, cached.total_allocated);
         }
 
         let entries = match fs::read_dir(path) {
@@ -179,10 +579,12 @@ impl Scanner {
                 if emit_entries {
                     self.emit_error(&path.display().to_string(), &e.to_string());
                 }
-                return (0, 0);
+                return (0, 0, 0);
             }
         };
 
+        let mut children: Vec<FileEntry> = Vec::new();
+
         for entry in entries {
             let entry = match entry {
                 Ok(entry) => entry,
@@ -193,7 +595,7 @@ impl Scanner {
             };
             // Check for control commands during iteration
             if !self.check_control() {
-                return (total_size, total_items);
+                return (total_size, total_items, total_allocated);
             }
 
             let entry_path = entry.path();
@@ -208,10 +610,16 @@ impl Scanner {
             let is_symlink = entry.file_type().map(|ft| ft.is_symlink()).unwrap_or(false);
             let is_dir = metadata.is_dir();
 
-            let (size, item_count) = if is_dir && !is_symlink {
-                self.scan_directory(&entry_path, emit_entries)
+            let (size, item_count, allocated_size) = if is_dir && !is_symlink {
+                self.scan_directory_internal(&entry_path, emit_entries, true)
             } else {
-                (metadata.len(), 1)
+                if self.find_duplicates && !is_dir && !is_symlink {
+                    self.dedup_by_size
+                        .entry(metadata.len())
+                        .or_default()
+                        .push(entry_path.display().to_string());
+                }
+                (metadata.len(), 1, allocated_bytes(&metadata))
             };
 
             let modified = metadata
@@ -220,7 +628,16 @@ impl Scanner {
                 .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
                 .map(|d| d.as_secs());
 
-            let file_type = self.detect_file_type(&entry_path);
+            let file_type = detect_file_type(&entry_path, self.detect_by_content);
+
+            if self.check_integrity
+                && !is_dir
+                && !is_symlink
+                && matches!(file_type.as_str(), "image" | "archive" | "document" | "audio")
+            {
+                self.integrity_candidates
+                    .push((entry_path.display().to_string(), file_type.clone()));
+            }
 
             let file_entry = FileEntry {
                 path: entry_path.display().to_string(),
@@ -229,6 +646,7 @@ impl Scanner {
                     .map(|n| n.to_string_lossy().to_string())
                     .unwrap_or_default(),
                 size,
+                allocated_size,
                 is_dir,
                 is_symlink,
                 modified,
@@ -236,12 +654,31 @@ impl Scanner {
                 file_type,
             };
 
+            if self.cache_dir.is_some() {
+                children.push(file_entry.clone());
+            }
+
             if emit_entries {
                 self.emit_entry(file_entry);
             }
 
+            if self.progress_enabled && !is_dir {
+                self.progress_items_done += 1;
+                self.progress_bytes_done += size;
+                self.maybe_emit_progress();
+            }
+
             total_size += size;
             total_items += item_count;
+            total_allocated += allocated_size;
+        }
+
+        // A directory's inode itself occupies blocks on disk, same as `du`
+        // reports; fold those in alongside the children's rollup so the
+        // allocated total for a subtree matches `du -sk` rather than just
+        // summing leaf files.
+        if let Ok(own_metadata) = fs::metadata(path) {
+            total_allocated += allocated_bytes(&own_metadata);
         }
 
         if emit_entries {
@@ -251,28 +688,92 @@ impl Scanner {
             });
         }
 
-        (total_size, total_items)
+        if self.cache_dir.is_some() {
+            let modified = dir_modified(path);
+            self.remember_cached_dir(
+                path,
+                CachedDir {
+                    modified,
+                    item_count: total_items,
+                    total_size,
+                    total_allocated,
+                    children,
+                },
+            );
+        }
+
+        (total_size, total_items, total_allocated)
     }
 
-    fn detect_file_type(&self, path: &Path) -> String {
-        let ext = path
-            .extension()
-            .map(|e| e.to_string_lossy().to_lowercase())
-            .unwrap_or_default();
+    /// Returns the cached entry for `path` if its mtime, direct child names,
+    /// and each child's own state still match what was recorded last time.
+    /// The directory-level mtime check alone isn't enough: on Linux,
+    /// overwriting a file in place (same name, new contents) doesn't update
+    /// its parent directory's mtime, so a per-child comparison is what
+    /// actually catches an edited file rather than just an added or removed
+    /// one.
+    ///
+    /// A directory child is compared differently from a file child:
+    /// `cached_child.size` for a directory is the *rolled-up recursive
+    /// total* written by `scan_directory_internal`, not the on-disk size of
+    /// the directory inode entry itself, so comparing it against
+    /// `entry.metadata().len()` would compare unrelated numbers and almost
+    /// never match. Instead, a directory child is considered unchanged only
+    /// if its own cached record is itself still valid, checked by recursing
+    /// into `reuse_cached_dir` for that child's path — which in turn catches
+    /// edits arbitrarily deep in that subtree, not just at this level.
+    fn reuse_cached_dir(&self, path: &Path) -> Option<CachedDir> {
+        let cached = self.cache.get(&path.display().to_string())?;
 
-        match ext.as_str() {
-            "mp4" | "mov" | "avi" | "mkv" | "wmv" | "flv" | "webm" => "video",
-            "mp3" | "wav" | "aac" | "flac" | "ogg" | "m4a" => "audio",
-            "jpg" | "jpeg" | "png" | "gif" | "bmp" | "svg" | "webp" | "heic" => "image",
-            "swift" | "rs" | "js" | "ts" | "py" | "rb" | "go" | "java" | "c" | "cpp" | "h" => "code",
-            "zip" | "tar" | "gz" | "rar" | "7z" | "dmg" | "iso" => "archive",
-            "app" | "exe" | "dll" | "so" | "dylib" => "application",
-            "plist" | "kext" => "system",
-            "cache" | "tmp" | "log" => "cache",
-            "pdf" | "doc" | "docx" | "txt" | "md" | "rtf" | "xls" | "xlsx" => "document",
-            _ => "other",
+        if dir_modified(path) != cached.modified {
+            return None;
         }
-        .to_string()
+
+        let cached_by_name: HashMap<&str, &FileEntry> = cached
+            .children
+            .iter()
+            .map(|child| (child.name.as_str(), child))
+            .collect();
+
+        let mut seen = 0usize;
+        for entry in fs::read_dir(path).ok()? {
+            let entry = entry.ok()?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let cached_child = cached_by_name.get(name.as_str())?;
+
+            let metadata = entry.metadata().ok()?;
+            if metadata.is_dir() != cached_child.is_dir {
+                return None;
+            }
+
+            if metadata.is_dir() {
+                self.reuse_cached_dir(&entry.path())?;
+            } else {
+                if metadata.len() != cached_child.size {
+                    return None;
+                }
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs());
+                if modified != cached_child.modified {
+                    return None;
+                }
+            }
+
+            seen += 1;
+        }
+
+        if seen != cached.children.len() {
+            return None;
+        }
+
+        Some(cached.clone())
+    }
+
+    fn remember_cached_dir(&mut self, path: &Path, entry: CachedDir) {
+        self.pending_cache.insert(path.display().to_string(), entry);
     }
 
     fn emit(&mut self, message: ScanMessage) {
@@ -313,6 +814,353 @@ impl Default for Scanner {
     }
 }
 
+fn detect_file_type(path: &Path, detect_by_content: bool) -> String {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let by_extension = match ext.as_str() {
+        "mp4" | "mov" | "avi" | "mkv" | "wmv" | "flv" | "webm" => "video",
+        "mp3" | "wav" | "aac" | "flac" | "ogg" | "m4a" => "audio",
+        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "svg" | "webp" | "heic" => "image",
+        "swift" | "rs" | "js" | "ts" | "py" | "rb" | "go" | "java" | "c" | "cpp" | "h" => "code",
+        "zip" | "tar" | "gz" | "rar" | "7z" | "dmg" | "iso" => "archive",
+        "app" | "exe" | "dll" | "so" | "dylib" => "application",
+        "plist" | "kext" => "system",
+        "cache" | "tmp" | "log" => "cache",
+        "pdf" | "doc" | "docx" | "txt" | "md" | "rtf" | "xls" | "xlsx" => "document",
+        _ => "other",
+    };
+
+    if by_extension != "other" || !detect_by_content {
+        return by_extension.to_string();
+    }
+
+    detect_file_type_by_content(path)
+        .unwrap_or(by_extension)
+        .to_string()
+}
+
+/// Magic-byte signatures checked when extension matching comes up empty
+/// (extensionless files, misnamed files, things like `Makefile`). Only the
+/// leading bytes are read, so this stays cheap even on large files.
+const MAGIC_SIGNATURES: &[(&[u8], &str)] = &[
+    (b"%PDF", "document"),
+    (b"PK\x03\x04", "archive"),
+    (b"\xFF\xD8\xFF", "image"),
+    (b"\x89PNG", "image"),
+    (b"\x7FELF", "application"),
+    (b"MZ", "application"),
+    (b"#!", "code"),
+];
+
+fn detect_file_type_by_content(path: &Path) -> Option<&'static str> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut header = [0u8; 8];
+    let read = file.read(&mut header).ok()?;
+    let header = &header[..read];
+
+    MAGIC_SIGNATURES
+        .iter()
+        .find(|(signature, _)| header.starts_with(signature))
+        .map(|(_, file_type)| *file_type)
+}
+
+/// Bytes actually allocated on disk, matching what `du` reports: the
+/// filesystem's block count times the fixed 512-byte unit `st_blocks` is
+/// always expressed in (this is independent of the filesystem's actual
+/// block size). Diverges from `metadata.len()` for sparse files and for
+/// files smaller than one block.
+fn allocated_bytes(metadata: &fs::Metadata) -> u64 {
+    metadata.blocks() * 512
+}
+
+/// A directory's own mtime (seconds since epoch), used as the cheap first
+/// check for whether its contents may have changed since the last scan.
+fn dir_modified(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+/// Shared state for `Scanner::scan_parallel`. All fields are safe to read
+/// and write from any worker thread without holding `&mut Scanner`.
+struct ParallelState {
+    writer: Arc<Mutex<BufWriter<io::Stdout>>>,
+    paused: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+    watcher_done: AtomicBool,
+    total_size: AtomicU64,
+    total_items: AtomicU64,
+    total_allocated: AtomicU64,
+    detect_by_content: bool,
+    /// Parks workers while paused instead of having each one poll its own
+    /// sleep loop. Only `run_control_watcher` ever flips `paused` and wakes
+    /// this, so the coordinator is the single place pause/resume is decided;
+    /// workers just wait on it.
+    pause_lock: Mutex<()>,
+    pause_condvar: Condvar,
+}
+
+fn emit_parallel(state: &ParallelState, message: ScanMessage) {
+    if let (Ok(json), Ok(mut writer)) = (serde_json::to_string(&message), state.writer.lock()) {
+        writeln!(writer, "{}", json).ok();
+        writer.flush().ok();
+    }
+}
+
+/// Runs on a single dedicated thread for the lifetime of `scan_parallel`.
+/// This is the only place that drains `control_rx`, so pausing/resuming
+/// never races multiple workers against the same channel; workers merely
+/// read the `paused`/`cancelled` flags this loop maintains.
+fn run_control_watcher(rx: &Receiver<ControlCommand>, state: &ParallelState) {
+    loop {
+        if state.watcher_done.load(Ordering::Relaxed) {
+            return;
+        }
+        match rx.recv_timeout(Duration::from_millis(50)) {
+            Ok(ControlCommand::Pause) => {
+                state.paused.store(true, Ordering::Relaxed);
+                emit_parallel(
+                    state,
+                    ScanMessage::Status {
+                        status: "paused".to_string(),
+                    },
+                );
+            }
+            Ok(ControlCommand::Resume) => {
+                // Flip the flag under `pause_lock` so a worker between its
+                // predicate check and its wait can't miss the wakeup.
+                {
+                    let _guard = state.pause_lock.lock().unwrap_or_else(|e| e.into_inner());
+                    state.paused.store(false, Ordering::Relaxed);
+                }
+                state.pause_condvar.notify_all();
+                emit_parallel(
+                    state,
+                    ScanMessage::Status {
+                        status: "resumed".to_string(),
+                    },
+                );
+            }
+            Ok(ControlCommand::Cancel) => {
+                {
+                    let _guard = state.pause_lock.lock().unwrap_or_else(|e| e.into_inner());
+                    state.cancelled.store(true, Ordering::Relaxed);
+                }
+                state.pause_condvar.notify_all();
+                emit_parallel(
+                    state,
+                    ScanMessage::Status {
+                        status: "cancelled".to_string(),
+                    },
+                );
+            }
+            Ok(ControlCommand::Refresh(path)) => {
+                emit_parallel(
+                    state,
+                    ScanMessage::Status {
+                        status: format!("refreshing:{}", path),
+                    },
+                );
+            }
+            Err(_) => continue, // timeout: re-check watcher_done/cancelled
+        }
+    }
+}
+
+/// Worker-side checkpoint: reads the shared flags only, never touches
+/// `control_rx`. While paused, parks on `pause_condvar` instead of spinning
+/// its own sleep loop; `run_control_watcher` is the only thread that flips
+/// `paused`/`cancelled` and wakes waiters, so pause handling stays
+/// centralized in the coordinator rather than every worker polling
+/// independently.
+fn parallel_checkpoint(state: &ParallelState) -> bool {
+    if state.cancelled.load(Ordering::Relaxed) {
+        return false;
+    }
+    if state.paused.load(Ordering::Relaxed) {
+        let guard = state.pause_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let _guard = state
+            .pause_condvar
+            .wait_while(guard, |_| {
+                state.paused.load(Ordering::Relaxed) && !state.cancelled.load(Ordering::Relaxed)
+            })
+            .unwrap_or_else(|e| e.into_inner());
+    }
+    !state.cancelled.load(Ordering::Relaxed)
+}
+
+fn scan_directory_parallel(path: &Path, state: &ParallelState) -> (u64, u64, u64) {
+    if !parallel_checkpoint(state) {
+        return (0, 0, 0);
+    }
+
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            emit_parallel(
+                state,
+                ScanMessage::Error {
+                    path: path.display().to_string(),
+                    message: e.to_string(),
+                },
+            );
+            return (0, 0, 0);
+        }
+    };
+
+    let mut subdirs: Vec<(PathBuf, fs::Metadata)> = Vec::new();
+    let mut files: Vec<(PathBuf, fs::Metadata, bool)> = Vec::new();
+
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                emit_parallel(
+                    state,
+                    ScanMessage::Error {
+                        path: path.display().to_string(),
+                        message: e.to_string(),
+                    },
+                );
+                continue;
+            }
+        };
+
+        let entry_path = entry.path();
+        let metadata = match entry.metadata() {
+            Ok(m) => m,
+            Err(e) => {
+                emit_parallel(
+                    state,
+                    ScanMessage::Error {
+                        path: entry_path.display().to_string(),
+                        message: e.to_string(),
+                    },
+                );
+                continue;
+            }
+        };
+
+        let is_symlink = entry.file_type().map(|ft| ft.is_symlink()).unwrap_or(false);
+        if metadata.is_dir() && !is_symlink {
+            subdirs.push((entry_path, metadata));
+        } else {
+            files.push((entry_path, metadata, is_symlink));
+        }
+    }
+
+    // Sibling subdirectories are the unit of work-stealing: rayon's pool
+    // walks them concurrently, and this directory's own files are emitted
+    // once they have all finished. Each subdirectory also gets its own `ScanMessage::Entry`,
+    // rolled up from its recursive totals, matching the sequential path so
+    // a consumer of the JSON stream sees the same shape from either mode.
+    let (dir_size, dir_items, dir_allocated) = subdirs
+        .par_iter()
+        .map(|(subdir_path, subdir_metadata)| {
+            // `scan_directory_parallel` already folds the subdirectory's own
+            // inode blocks (and its atomic contribution) into what it
+            // returns, the same way it does when called on the root.
+            let (size, item_count, allocated_size) = scan_directory_parallel(subdir_path, state);
+
+            let modified = subdir_metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs());
+
+            emit_parallel(
+                state,
+                ScanMessage::Entry(FileEntry {
+                    path: subdir_path.display().to_string(),
+                    name: subdir_path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    size,
+                    allocated_size,
+                    is_dir: true,
+                    is_symlink: false,
+                    modified,
+                    item_count,
+                    file_type: detect_file_type(subdir_path, state.detect_by_content),
+                }),
+            );
+
+            (size, item_count, allocated_size)
+        })
+        .reduce(|| (0u64, 0u64, 0u64), |a, b| (a.0 + b.0, a.1 + b.1, a.2 + b.2));
+
+    let mut total_size = dir_size;
+    let mut total_items = dir_items;
+    let mut total_allocated = dir_allocated;
+
+    for (entry_path, metadata, is_symlink) in files {
+        if !parallel_checkpoint(state) {
+            break;
+        }
+
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+
+        let file_entry = FileEntry {
+            path: entry_path.display().to_string(),
+            name: entry_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            size: metadata.len(),
+            allocated_size: allocated_bytes(&metadata),
+            is_dir: metadata.is_dir(),
+            is_symlink,
+            modified,
+            item_count: 1,
+            file_type: detect_file_type(&entry_path, state.detect_by_content),
+        };
+
+        total_size += file_entry.size;
+        total_items += 1;
+        total_allocated += file_entry.allocated_size;
+        state.total_size.fetch_add(file_entry.size, Ordering::Relaxed);
+        state.total_items.fetch_add(1, Ordering::Relaxed);
+        state
+            .total_allocated
+            .fetch_add(file_entry.allocated_size, Ordering::Relaxed);
+        emit_parallel(state, ScanMessage::Entry(file_entry));
+    }
+
+    // Fold in this directory's own inode blocks, same as the sequential
+    // path: every call (including the one made directly on the root) counts
+    // toward the running total, since a directory is never itself visited
+    // as one of its parent's "files".
+    if let Ok(own_metadata) = fs::metadata(path) {
+        let own_allocated = allocated_bytes(&own_metadata);
+        total_allocated += own_allocated;
+        state
+            .total_allocated
+            .fetch_add(own_allocated, Ordering::Relaxed);
+    }
+
+    emit_parallel(
+        state,
+        ScanMessage::FolderComplete {
+            path: path.display().to_string(),
+            total_size,
+        },
+    );
+
+    (total_size, total_items, total_allocated)
+}
+
 pub fn compute_directory_totals(path: &Path) -> io::Result<ScanTotals> {
     if !path.exists() {
         return Err(io::Error::new(
@@ -322,42 +1170,35 @@ pub fn compute_directory_totals(path: &Path) -> io::Result<ScanTotals> {
     }
 
     let mut scanner = Scanner::new();
-    let (total_size, total_items) = scanner.scan_directory(path, false);
+    let (total_size, total_items, total_allocated) = scanner.scan_directory(path, false);
     Ok(ScanTotals {
         total_size,
         total_items,
+        total_allocated,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{create_temp_dir, write_bytes};
     use std::fs::{self, File};
-    use std::io::Write;
     use std::os::unix::fs as unix_fs;
-    use std::path::PathBuf;
-    use std::time::{SystemTime, UNIX_EPOCH};
+    use std::time::UNIX_EPOCH;
 
-    fn create_temp_dir(test_name: &str) -> PathBuf {
-        let mut path = std::env::temp_dir();
-        let nanos = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-        path.push(format!(
-            "diskspice_scanner_test_{}_{}_{}",
-            test_name,
-            std::process::id(),
-            nanos
-        ));
-        fs::create_dir_all(&path).expect("create temp dir");
-        path
-    }
+    #[test]
+    fn detect_file_type_falls_back_to_magic_bytes_when_enabled() {
+        let root = create_temp_dir("magic_bytes");
+        let extensionless = root.join("report");
+        File::create(&extensionless)
+            .expect("create file")
+            .write_all(b"%PDF-1.4 rest of file")
+            .expect("write header");
+
+        assert_eq!(detect_file_type(&extensionless, false), "other");
+        assert_eq!(detect_file_type(&extensionless, true), "document");
 
-    fn write_bytes(path: &Path, size: usize) {
-        let mut file = File::create(path).expect("create file");
-        let buffer = vec![0u8; size];
-        file.write_all(&buffer).expect("write bytes");
+        fs::remove_dir_all(root).expect("cleanup");
     }
 
     #[test]
@@ -368,7 +1209,7 @@ mod tests {
         write_bytes(&root.join("sub/b.bin"), 20);
 
         let mut scanner = Scanner::new();
-        let (total_size, total_items) = scanner.scan_directory(&root, false);
+        let (total_size, total_items, _total_allocated) = scanner.scan_directory(&root, false);
 
         assert_eq!(total_size, 30);
         assert_eq!(total_items, 2);
@@ -376,6 +1217,49 @@ mod tests {
         fs::remove_dir_all(root).expect("cleanup");
     }
 
+    #[test]
+    fn scan_directory_reports_allocated_size_for_sparse_file() {
+        let root = create_temp_dir("sparse");
+        let sparse_path = root.join("sparse.bin");
+        {
+            let file = File::create(&sparse_path).expect("create file");
+            file.set_len(1024 * 1024).expect("punch a hole with set_len");
+        }
+
+        let mut scanner = Scanner::new();
+        let (total_size, _total_items, total_allocated) = scanner.scan_directory(&root, false);
+
+        assert_eq!(total_size, 1024 * 1024);
+        assert!(
+            total_allocated < total_size,
+            "a sparse file should allocate far fewer bytes than its logical length"
+        );
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
+    #[test]
+    fn scan_directory_allocated_size_includes_each_directorys_own_blocks() {
+        let root = create_temp_dir("dir_blocks");
+        fs::create_dir_all(root.join("sub")).expect("create subdir");
+        write_bytes(&root.join("sub/a.bin"), 10);
+
+        let mut scanner = Scanner::new();
+        let (_total_size, _total_items, total_allocated) = scanner.scan_directory(&root, false);
+
+        let file_allocated = allocated_bytes(&fs::metadata(root.join("sub/a.bin")).unwrap());
+        let root_dir_allocated = allocated_bytes(&fs::metadata(&root).unwrap());
+        let sub_dir_allocated = allocated_bytes(&fs::metadata(root.join("sub")).unwrap());
+
+        assert_eq!(
+            total_allocated,
+            file_allocated + root_dir_allocated + sub_dir_allocated,
+            "the rollup should fold in every directory's own inode blocks, not just leaf files"
+        );
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
     #[test]
     fn scan_directory_does_not_recurse_symlinked_dir() {
         let root = create_temp_dir("symlink");
@@ -384,7 +1268,7 @@ mod tests {
         unix_fs::symlink(root.join("target"), root.join("link")).expect("symlink");
 
         let mut scanner = Scanner::new();
-        let (_total_size, total_items) = scanner.scan_directory(&root, false);
+        let (_total_size, total_items, _total_allocated) = scanner.scan_directory(&root, false);
 
         assert_eq!(total_items, 2, "counts only file + symlink entry");
 
@@ -406,4 +1290,275 @@ mod tests {
         let result = compute_directory_totals(&missing);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn scan_directory_parallel_matches_sequential_totals() {
+        let root = create_temp_dir("parallel");
+        fs::create_dir_all(root.join("a/b")).expect("create nested dirs");
+        fs::create_dir_all(root.join("c")).expect("create sibling dir");
+        write_bytes(&root.join("a/one.bin"), 100);
+        write_bytes(&root.join("a/b/two.bin"), 50);
+        write_bytes(&root.join("c/three.bin"), 25);
+
+        let mut scanner = Scanner::new();
+        let (seq_size, seq_items, seq_allocated) = scanner.scan_directory(&root, false);
+
+        let state = ParallelState {
+            writer: Arc::new(Mutex::new(BufWriter::new(io::stdout()))),
+            paused: Arc::new(AtomicBool::new(false)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            watcher_done: AtomicBool::new(false),
+            total_size: AtomicU64::new(0),
+            total_items: AtomicU64::new(0),
+            total_allocated: AtomicU64::new(0),
+            detect_by_content: false,
+            pause_lock: Mutex::new(()),
+            pause_condvar: Condvar::new(),
+        };
+        let (par_size, par_items, par_allocated) = scan_directory_parallel(&root, &state);
+
+        assert_eq!(par_size, seq_size);
+        assert_eq!(par_items, seq_items);
+        assert_eq!(par_allocated, seq_allocated);
+        assert_eq!(state.total_size.load(Ordering::Relaxed), seq_size);
+        assert_eq!(state.total_items.load(Ordering::Relaxed), seq_items);
+        assert_eq!(state.total_allocated.load(Ordering::Relaxed), seq_allocated);
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
+    #[test]
+    fn scan_parallel_leaves_control_channel_usable_for_a_later_refresh() {
+        let root = create_temp_dir("parallel_control");
+        write_bytes(&root.join("a.bin"), 10);
+
+        let (mut scanner, _control_tx) = Scanner::with_control_channel();
+        scanner.scan_parallel(&root.display().to_string());
+
+        assert!(
+            scanner.control_rx.is_some(),
+            "scan_parallel must not consume control_rx, or a later \
+             serve_refresh_requests() call silently no-ops forever"
+        );
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
+    #[test]
+    fn scan_with_cache_dir_persists_entries_to_disk() {
+        let root = create_temp_dir("cache_persist");
+        write_bytes(&root.join("a.txt"), 10);
+        let cache_dir = create_temp_dir("cache_persist_dir");
+
+        let mut scanner = Scanner::new();
+        scanner.set_cache_dir(cache_dir.clone());
+        scanner.scan_directory(&root, false);
+        scanner.flush_cache();
+
+        let reloaded = cache::load(&cache_dir);
+        assert_eq!(reloaded[&root.display().to_string()].item_count, 1);
+
+        fs::remove_dir_all(root).expect("cleanup");
+        fs::remove_dir_all(cache_dir).ok();
+    }
+
+    #[test]
+    fn reuse_cached_dir_matches_only_unchanged_contents() {
+        let root = create_temp_dir("cache_reuse");
+        write_bytes(&root.join("a.bin"), 10);
+        let cache_dir = create_temp_dir("cache_reuse_dir");
+
+        let mut scanner = Scanner::new();
+        scanner.set_cache_dir(cache_dir.clone());
+        scanner.scan_directory(&root, false);
+        scanner.flush_cache();
+        assert!(
+            scanner.reuse_cached_dir(&root).is_some(),
+            "unchanged directory should hit the cache"
+        );
+
+        write_bytes(&root.join("b.bin"), 7);
+        assert!(
+            scanner.reuse_cached_dir(&root).is_none(),
+            "a new child should invalidate the cached entry"
+        );
+
+        fs::remove_dir_all(root).expect("cleanup");
+        fs::remove_dir_all(cache_dir).ok();
+    }
+
+    #[test]
+    fn reuse_cached_dir_rejects_an_edited_child_even_with_unchanged_dir_mtime() {
+        let root = create_temp_dir("cache_edit");
+        write_bytes(&root.join("a.bin"), 10);
+        let cache_dir = create_temp_dir("cache_edit_dir");
+
+        let mut scanner = Scanner::new();
+        scanner.set_cache_dir(cache_dir.clone());
+        scanner.scan_directory(&root, false);
+        scanner.flush_cache();
+
+        let dir_mtime_before = dir_modified(&root);
+        write_bytes(&root.join("a.bin"), 999);
+        assert_eq!(
+            dir_modified(&root),
+            dir_mtime_before,
+            "overwriting a file in place must not be the thing that changes the parent mtime, \
+             or this test isn't exercising the bug"
+        );
+
+        assert!(
+            scanner.reuse_cached_dir(&root).is_none(),
+            "an edited child must invalidate the cache even though the directory's own mtime \
+             and child name set are unchanged"
+        );
+
+        fs::remove_dir_all(root).expect("cleanup");
+        fs::remove_dir_all(cache_dir).ok();
+    }
+
+    #[test]
+    fn reuse_cached_dir_hits_for_an_unchanged_subdirectory_child() {
+        let root = create_temp_dir("cache_subdir_reuse");
+        fs::create_dir_all(root.join("sub")).expect("create subdir");
+        write_bytes(&root.join("sub/a.bin"), 10);
+        let cache_dir = create_temp_dir("cache_subdir_reuse_dir");
+
+        let mut scanner = Scanner::new();
+        scanner.set_cache_dir(cache_dir.clone());
+        scanner.scan_directory(&root, false);
+        scanner.flush_cache();
+
+        // Reload from disk the way a fresh process would, so `reuse_cached_dir`
+        // is exercised against a persisted cache rather than the in-memory one
+        // this scan just built.
+        let mut reloaded = Scanner::new();
+        reloaded.set_cache_dir(cache_dir.clone());
+        reloaded.cache = cache::load(&cache_dir);
+        assert!(
+            reloaded.reuse_cached_dir(&root).is_some(),
+            "a directory child's rolled-up size must not be compared against its own inode \
+             entry size, or every tree with a subdirectory always misses the cache"
+        );
+
+        fs::remove_dir_all(root).expect("cleanup");
+        fs::remove_dir_all(cache_dir).ok();
+    }
+
+    #[test]
+    fn reuse_cached_dir_rejects_an_edit_nested_inside_a_subdirectory_child() {
+        let root = create_temp_dir("cache_subdir_edit");
+        fs::create_dir_all(root.join("sub")).expect("create subdir");
+        write_bytes(&root.join("sub/a.bin"), 10);
+        let cache_dir = create_temp_dir("cache_subdir_edit_dir");
+
+        let mut scanner = Scanner::new();
+        scanner.set_cache_dir(cache_dir.clone());
+        scanner.scan_directory(&root, false);
+        scanner.flush_cache();
+
+        let sub_mtime_before = dir_modified(&root.join("sub"));
+        write_bytes(&root.join("sub/a.bin"), 999);
+        assert_eq!(
+            dir_modified(&root.join("sub")),
+            sub_mtime_before,
+            "overwriting a file in place must not be the thing that changes its parent's \
+             mtime, or this test isn't exercising the bug"
+        );
+
+        let mut reloaded = Scanner::new();
+        reloaded.set_cache_dir(cache_dir.clone());
+        reloaded.cache = cache::load(&cache_dir);
+        assert!(
+            reloaded.reuse_cached_dir(&root).is_none(),
+            "an edit nested inside a subdirectory child must invalidate the root's cache entry \
+             too, even though the root's own mtime and child name set are unchanged"
+        );
+
+        fs::remove_dir_all(root).expect("cleanup");
+        fs::remove_dir_all(cache_dir).ok();
+    }
+
+    #[test]
+    fn scan_picks_up_new_files_after_cache_is_populated() {
+        let root = create_temp_dir("cache_rescan");
+        fs::create_dir_all(root.join("unchanged")).expect("create subdir");
+        write_bytes(&root.join("unchanged/a.bin"), 10);
+        write_bytes(&root.join("top.bin"), 5);
+        let cache_dir = create_temp_dir("cache_rescan_dir");
+
+        let mut scanner = Scanner::new();
+        scanner.set_cache_dir(cache_dir.clone());
+        scanner.scan(&root.display().to_string());
+
+        write_bytes(&root.join("new.bin"), 7);
+
+        let (total_size, total_items, _total_allocated) =
+            scanner.scan_directory(&root, false);
+
+        assert_eq!(total_items, 3, "picks up the newly added file");
+        assert_eq!(total_size, 10 + 5 + 7);
+
+        fs::remove_dir_all(root).expect("cleanup");
+        fs::remove_dir_all(cache_dir).ok();
+    }
+
+    #[test]
+    fn count_items_matches_scan_directory_item_count() {
+        let root = create_temp_dir("count_items");
+        write_bytes(&root.join("a.txt"), 10);
+        fs::create_dir_all(root.join("sub")).expect("create subdir");
+        write_bytes(&root.join("sub/b.bin"), 20);
+
+        let mut scanner = Scanner::new();
+        let counted = scanner.count_items(&root);
+        let (_total_size, total_items, _total_allocated) = scanner.scan_directory(&root, false);
+
+        assert_eq!(counted, total_items);
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
+    #[test]
+    fn scan_directory_accumulates_progress_when_enabled() {
+        let root = create_temp_dir("progress");
+        write_bytes(&root.join("a.txt"), 10);
+        write_bytes(&root.join("b.bin"), 20);
+
+        let mut scanner = Scanner::new();
+        scanner.set_progress(true);
+        let (total_size, total_items, _total_allocated) = scanner.scan_directory(&root, false);
+
+        assert_eq!(scanner.progress_items_done, total_items);
+        assert_eq!(scanner.progress_bytes_done, total_size);
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
+
+    #[test]
+    fn scan_directory_collects_integrity_candidates_by_file_type() {
+        let root = create_temp_dir("integrity_candidates");
+        write_bytes(&root.join("plain.txt"), 10); // "document", but not PDF-checked
+        {
+            let mut file = File::create(root.join("photo.jpg")).expect("create file");
+            file.write_all(&[0xFF, 0xD8, 0xFF, 0x00, 0xFF, 0xD9])
+                .expect("write jpeg bytes");
+        }
+
+        let mut scanner = Scanner::new();
+        scanner.set_check_integrity(true);
+        scanner.scan_directory(&root, false);
+
+        assert_eq!(
+            scanner.integrity_candidates.len(),
+            2,
+            "both document and image files are collected as candidates"
+        );
+        assert!(scanner
+            .integrity_candidates
+            .iter()
+            .any(|(path, kind)| path.ends_with("photo.jpg") && kind == "image"));
+
+        fs::remove_dir_all(root).expect("cleanup");
+    }
 }
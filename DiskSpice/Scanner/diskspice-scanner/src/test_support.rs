@@ -0,0 +1,43 @@
+//! Shared fixtures for this crate's `#[cfg(test)]` modules: every test file
+//! was independently growing its own copy of "make a scratch temp dir" and
+//! "write some bytes to a file", so they live here once instead.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Builds a unique path under the system temp dir for `test_name`, without
+/// creating it. `cache::tests` wants this: it needs a path it can assert is
+/// empty/missing.
+pub(crate) fn temp_dir_path(test_name: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    path.push(format!(
+        "diskspice_test_{}_{}_{}",
+        test_name,
+        std::process::id(),
+        nanos
+    ));
+    path
+}
+
+/// Same as [`temp_dir_path`], but creates the directory so tests can write
+/// into it right away.
+pub(crate) fn create_temp_dir(test_name: &str) -> PathBuf {
+    let path = temp_dir_path(test_name);
+    fs::create_dir_all(&path).expect("create temp dir");
+    path
+}
+
+pub(crate) fn write_file(path: &Path, contents: &[u8]) {
+    let mut file = File::create(path).expect("create file");
+    file.write_all(contents).expect("write contents");
+}
+
+pub(crate) fn write_bytes(path: &Path, size: usize) {
+    write_file(path, &vec![0u8; size]);
+}